@@ -1,9 +1,39 @@
-use rand::random;
+//! The Chip-8 core. It has no SDL or `rand` dependency of its own, so any frontend (the SDL
+//! binary in `main.rs`, the `wasm` module) can drive it from its own render/input loop and supply
+//! its own [`RandSource`]. This is plain `std` code, not `no_std` — `Box<dyn RandSource>` and
+//! `disassemble`'s `Vec` both assume an allocator is available unconditionally.
+
+mod instruction;
+pub use instruction::Instruction;
+use instruction::decode;
+
+/// A source of randomness for the `Cxkk` instruction. The core has no opinion on how randomness
+/// is produced, so frontends (SDL, wasm, ...) inject whichever source fits their platform.
+pub trait RandSource {
+    /// Returns the next random byte.
+    fn next_u8(&mut self) -> u8;
+}
 
 pub const SCREEN_WIDTH: u8 = 64;
 pub const SCREEN_HEIGHT: u8 = 32;
 const SCREEN_MEM_SIZE: usize = SCREEN_WIDTH as usize * SCREEN_HEIGHT as usize;
 
+/// Something that went wrong while decoding or executing an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// No known instruction matches the opcode found at `pc`.
+    UnknownInstruction { opcode: u16, pc: usize },
+
+    /// A `2nnn` call nested deeper than the 16-entry stack can hold.
+    StackOverflow,
+
+    /// A `00EE` return was executed with nothing on the stack.
+    StackUnderflow,
+
+    /// An instruction referenced a memory address outside of the Chip-8's 4 KiB RAM.
+    AddressOutOfBounds,
+}
+
 const FONT_ADDRESS: u16 = 0x50;
 const FONT_DATA: [u8; 80] = [0xF0, 0x90, 0x90, 0x90, 0xF0, 0x20, 0x60, 0x20, 0x20, 0x70, 0xF0, 0x10,
     0xF0, 0x80, 0xF0, 0xF0, 0x10, 0xF0, 0x10, 0xF0, 0x90, 0x90, 0xF0, 0x10, 0x10, 0xF0, 0x80, 0xF0,
@@ -14,6 +44,41 @@ const FONT_DATA: [u8; 80] = [0xF0, 0x90, 0x90, 0x90, 0xF0, 0x20, 0x60, 0x20, 0x2
 
 const ROM_START_ADDRESS: usize = 0x200;
 
+/// Configures behavior that differs between real-world Chip-8 interpreters. Different ROMs were
+/// written against different conventions for a handful of opcodes, so these are exposed instead
+/// of being hardcoded.
+#[derive(Clone, Copy)]
+pub struct Quirks {
+    /// Whether `8xy6`/`8xyE` shift Vx in place, instead of first copying Vy into Vx.
+    pub shift: bool,
+
+    /// Whether `Fx55`/`Fx65` leave `i_register` unchanged, instead of incrementing it by x + 1.
+    pub load_store_no_increment: bool,
+
+    /// Whether `Bnnn` is instead treated as `Bxnn`, jumping to `nnn + Vx` rather than `nnn + V0`.
+    pub jump_uses_vx: bool,
+
+    /// Whether `Fx1E` sets VF when `i_register` overflows past 0x0FFF.
+    pub overflow_flag: bool,
+
+    /// Whether `Dxyn` clips sprites at the edges of the screen, instead of wrapping them around
+    /// to the opposite edge.
+    pub clip_sprites: bool,
+}
+
+impl Default for Quirks {
+    /// The default quirks match the behavior of the original COSMAC VIP interpreter.
+    fn default() -> Quirks {
+        Quirks {
+            shift: false,
+            load_store_no_increment: false,
+            jump_uses_vx: false,
+            overflow_flag: false,
+            clip_sprites: true,
+        }
+    }
+}
+
 /// An instance of the Chip-8 emulator.
 pub struct Chip8 {
     /// The Chip-8's RAM.
@@ -47,244 +112,297 @@ pub struct Chip8 {
     /// register to store the pressed key into.
     waiting_for_key: Option<u8>,
 
-    /// Whether to use the alternative version of the 8xy6 and 8xyE instructions. Some roms may
-    /// expect those instructions to behave differently.
-    alternative_shift_mode: bool,
+    /// The set of interpreter behaviors this instance should emulate.
+    quirks: Quirks,
+
+    /// Where the `Cxkk` instruction gets its randomness from.
+    rand_source: Box<dyn RandSource>,
 
     /// How many CPU cycles to perform each frame.
     pub cycles_per_frame: u32,
+
+    /// Set to `true` whenever `screen` is mutated by the `00E0` or `Dxyn` instructions. A
+    /// frontend should re-upload the framebuffer when this is set, then reset it to `false`.
+    pub draw_flag: bool,
 }
 
 /// Array that describes which keys of the Chip-8 are currently pressed.
 pub type KeyboardState = [bool; 16];
 
 impl Chip8 {
-    /// Performs a single instruction cycle.
-    fn cycle(&mut self, keyboard_state: &KeyboardState) {
-        if let Some(reg) = self.waiting_for_key {
-            let key = keyboard_state.iter().position(|k| *k);
-            if let Some(k) = key {
-                self.v_registers[reg as usize] = k as u8;
-                self.waiting_for_key = None;
+    /// Whether the sound timer is currently active and a tone should be playing.
+    pub fn is_beeping(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    /// Executes a single already-decoded instruction, advancing `pc` as appropriate.
+    fn execute(&mut self, instruction: Instruction, keyboard_state: &KeyboardState) -> Result<(), Chip8Error> {
+        // by how much to increment pc after the instruction is done
+        let mut increment_pc = 2;
+
+        match instruction {
+            Instruction::ClearScreen => {
+                self.screen.fill(0);
+                self.draw_flag = true;
             }
-        } else {
-            let instr_low = self.memory[self.pc + 1];
-            let instr_high = self.memory[self.pc];
-            let instruction = instr_low as u16 + ((instr_high as u16) << 8);
-            let high_nibble = instr_high >> 4;
-            let low_nibble = instr_low & 0xf;
-            let x = (instruction >> 8 & 0xf) as usize;
-            let y = (instruction >> 4 & 0xf) as usize;
-
-            // by how much to increment pc after the instruction is done
-            let mut increment_pc = 2;
-
-            match high_nibble {
-                0 if instruction == 0x00E0 => {
-                    // 00E0: clear screen
-                    self.screen.fill(0);
-                }
-                0 if instruction == 0x00EE => {
-                    // 00EE: return from subroutine
-                    self.stack_pointer -= 1;
-                    self.pc = self.stack[self.stack_pointer] as usize;
+            Instruction::Return => {
+                if self.stack_pointer == 0 {
+                    return Err(Chip8Error::StackUnderflow);
                 }
-                1 => {
-                    // 1nnn: jump to address nnn
-                    self.pc = instruction as usize & 0xfff;
+                self.stack_pointer -= 1;
+                self.pc = self.stack[self.stack_pointer] as usize;
+            }
+            Instruction::Jump(nnn) => {
+                self.pc = nnn as usize;
 
-                    increment_pc = 0;
+                increment_pc = 0;
+            }
+            Instruction::Call(nnn) => {
+                if self.stack_pointer >= self.stack.len() {
+                    return Err(Chip8Error::StackOverflow);
                 }
-                2 => {
-                    // 2nnn: call subroutine at nnn
-                    self.stack[self.stack_pointer] = self.pc as u16;
-                    self.stack_pointer += 1;
-                    self.pc = instruction as usize & 0xfff;
+                self.stack[self.stack_pointer] = self.pc as u16;
+                self.stack_pointer += 1;
+                self.pc = nnn as usize;
 
-                    increment_pc = 0;
-                }
-                3 => {
-                    // 3xkk: skip next instruction if Vx == kk
-                    if self.v_registers[x] == instr_low {
-                        self.pc += 2;
-                    }
-                }
-                4 => {
-                    // 4xkk: skip next instruction if Vx != kk
-                    if self.v_registers[x] != instr_low {
-                        self.pc += 2;
-                    }
-                }
-                5 if low_nibble == 0 => {
-                    // 5xy0: skip next instruction if Vx == Vy
-                    if self.v_registers[x] == self.v_registers[y] {
-                        self.pc += 2;
-                    }
-                }
-                6 => {
-                    // 6xkk: set Vx = kk
-                    self.v_registers[x] = instr_low;
-                }
-                7 => {
-                    // 7xkk: set Vx = Vx + kk
-                    self.v_registers[x] = self.v_registers[x].wrapping_add(instr_low);
-                }
-                8 if low_nibble == 0 => {
-                    // 8xy0: set Vx = Vy
-                    self.v_registers[x] = self.v_registers[y];
-                }
-                8 if low_nibble == 1 => {
-                    // 8xy1: set Vx = Vx | Vy
-                    self.v_registers[x] = self.v_registers[x] | self.v_registers[y];
-                }
-                8 if low_nibble == 2 => {
-                    // 8xy2: set Vx = Vx & Vy
-                    self.v_registers[x] = self.v_registers[x] & self.v_registers[y];
-                }
-                8 if low_nibble == 3 => {
-                    // 8xy3: set Vx = Vx ^ Vy
-                    self.v_registers[x] = self.v_registers[x] ^ self.v_registers[y];
-                }
-                8 if low_nibble == 4 => {
-                    // 8xy4: set Vx = Vx + Vy, VF = carry
-                    let result = self.v_registers[x] as u16 + self.v_registers[y] as u16;
-                    self.v_registers[0xf] = (result >> 8 & 1) as u8;
-                    self.v_registers[x] = result as u8;
-                }
-                8 if low_nibble == 5 => {
-                    // 8xy5: set Vx = Vx - Vy, VF = not borrow
-                    self.v_registers[0xf] = if self.v_registers[x] > self.v_registers[y] { 0 } else { 1 };
-                    self.v_registers[x] = self.v_registers[x].wrapping_sub(self.v_registers[y]);
-                }
-                8 if low_nibble == 6 => {
-                    // 8xy6: set Vx = Vy >> 1, VF = LSB(Vx)
-                    let other = if self.alternative_shift_mode { x } else { y };
-                    self.v_registers[0xf] = self.v_registers[other] & 1;
-                    self.v_registers[x] = self.v_registers[other] >> 1;
-                }
-                8 if low_nibble == 7 => {
-                    // 8xy7: set Vx = Vy - Vx, VF = not borrow
-                    self.v_registers[0xf] = u8::from(self.v_registers[y] > self.v_registers[x]);
-                    self.v_registers[x] = self.v_registers[y].wrapping_sub(self.v_registers[x]);
+                increment_pc = 0;
+            }
+            Instruction::SkipEqualImm { x, kk } => {
+                if self.v_registers[x as usize] == kk {
+                    self.pc += 2;
                 }
-                8 if low_nibble == 0xE => {
-                    // 8xyE: set Vx = Vy << 1, VF = MSB(Vx)
-                    let other = if self.alternative_shift_mode { x } else { y };
-                    self.v_registers[0xf] = self.v_registers[other] & 0x80;
-                    self.v_registers[x] = self.v_registers[other] << 1;
+            }
+            Instruction::SkipNotEqualImm { x, kk } => {
+                if self.v_registers[x as usize] != kk {
+                    self.pc += 2;
                 }
-                9 if low_nibble == 0 => {
-                    // 9xy0: skip next instruction if Vx != Vy
-                    if self.v_registers[x] != self.v_registers[y] {
-                        self.pc += 2;
-                    }
+            }
+            Instruction::SkipEqual { x, y } => {
+                if self.v_registers[x as usize] == self.v_registers[y as usize] {
+                    self.pc += 2;
                 }
-                0xA => {
-                    // Annn: set I = nnn
-                    self.i_register = instruction & 0xfff;
+            }
+            Instruction::SetReg { x, kk } => {
+                self.v_registers[x as usize] = kk;
+            }
+            Instruction::AddReg { x, kk } => {
+                self.v_registers[x as usize] = self.v_registers[x as usize].wrapping_add(kk);
+            }
+            Instruction::Copy { x, y } => {
+                self.v_registers[x as usize] = self.v_registers[y as usize];
+            }
+            Instruction::Or { x, y } => {
+                self.v_registers[x as usize] = self.v_registers[x as usize] | self.v_registers[y as usize];
+            }
+            Instruction::And { x, y } => {
+                self.v_registers[x as usize] = self.v_registers[x as usize] & self.v_registers[y as usize];
+            }
+            Instruction::Xor { x, y } => {
+                self.v_registers[x as usize] = self.v_registers[x as usize] ^ self.v_registers[y as usize];
+            }
+            Instruction::Add { x, y } => {
+                let result = self.v_registers[x as usize] as u16 + self.v_registers[y as usize] as u16;
+                self.v_registers[0xf] = (result >> 8 & 1) as u8;
+                self.v_registers[x as usize] = result as u8;
+            }
+            Instruction::Sub { x, y } => {
+                self.v_registers[0xf] = u8::from(self.v_registers[x as usize] >= self.v_registers[y as usize]);
+                self.v_registers[x as usize] = self.v_registers[x as usize].wrapping_sub(self.v_registers[y as usize]);
+            }
+            Instruction::ShiftRight { x, y } => {
+                let other = if self.quirks.shift { x } else { y } as usize;
+                self.v_registers[0xf] = self.v_registers[other] & 1;
+                self.v_registers[x as usize] = self.v_registers[other] >> 1;
+            }
+            Instruction::SubReverse { x, y } => {
+                self.v_registers[0xf] = u8::from(self.v_registers[y as usize] >= self.v_registers[x as usize]);
+                self.v_registers[x as usize] = self.v_registers[y as usize].wrapping_sub(self.v_registers[x as usize]);
+            }
+            Instruction::ShiftLeft { x, y } => {
+                let other = if self.quirks.shift { x } else { y } as usize;
+                self.v_registers[0xf] = self.v_registers[other] >> 7;
+                self.v_registers[x as usize] = self.v_registers[other] << 1;
+            }
+            Instruction::SkipNotEqual { x, y } => {
+                if self.v_registers[x as usize] != self.v_registers[y as usize] {
+                    self.pc += 2;
                 }
-                0xB => {
-                    // Bnnn: jump to V0 + nnn
-                    self.pc = (instruction as usize & 0xfff) + self.v_registers[0] as usize;
+            }
+            Instruction::SetIndex(nnn) => {
+                self.i_register = nnn;
+            }
+            Instruction::JumpWithOffset(nnn) => {
+                let offset_register = if self.quirks.jump_uses_vx { (nnn >> 8 & 0xf) as usize } else { 0 };
+                self.pc = nnn as usize + self.v_registers[offset_register] as usize;
 
-                    increment_pc = 0;
-                }
-                0xC => {
-                    // Cxkk: set Vx = random & kk
-                    self.v_registers[x] = random::<u8>() & instr_low;
-                }
-                0xD => {
-                    // Dxyn: draw n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
-                    self.v_registers[0xF] = 0;
-                    for iy in 0..low_nibble {
-                        let row = self.memory[self.i_register as usize + iy as usize];
-                        for ix in 0..8 {
-                            let dx = (self.v_registers[x] + ix) % SCREEN_WIDTH;
-                            let dy = (self.v_registers[y] + iy) % SCREEN_HEIGHT;
-                            let index = dy as usize * SCREEN_WIDTH as usize + dx as usize;
-                            let prev_pixel = self.screen[index];
-                            let pixel = (row >> (7 - ix) & 1) * 255;
-                            if prev_pixel == 255 && pixel == 255 {
-                                self.v_registers[0xF] = 1;
-                            }
-                            self.screen[index] = prev_pixel ^ pixel;
-                        }
+                increment_pc = 0;
+            }
+            Instruction::Random { x, kk } => {
+                self.v_registers[x as usize] = self.rand_source.next_u8() & kk;
+            }
+            Instruction::Draw { x, y, n } => {
+                self.v_registers[0xF] = 0;
+                self.draw_flag = true;
+                for iy in 0..n {
+                    let row_address = self.i_register as usize + iy as usize;
+                    if row_address >= self.memory.len() {
+                        return Err(Chip8Error::AddressOutOfBounds);
                     }
-                }
-                0xE if instr_low == 0x9E => {
-                    // Ex9E: skip next instruction if key Vx is pressed
-                    if keyboard_state[self.v_registers[x] as usize] {
-                        self.pc += 2;
+                    let row = self.memory[row_address];
+                    let raw_dy = self.v_registers[y as usize] as u16 + iy as u16;
+                    if self.quirks.clip_sprites && raw_dy >= SCREEN_HEIGHT as u16 {
+                        continue;
                     }
-                }
-                0xE if instr_low == 0xA1 => {
-                    // Ex9E: skip next instruction if key Vx is not pressed
-                    if !keyboard_state[self.v_registers[x] as usize] {
-                        self.pc += 2;
+                    let dy = (raw_dy % SCREEN_HEIGHT as u16) as u8;
+                    for ix in 0..8 {
+                        let raw_dx = self.v_registers[x as usize] as u16 + ix as u16;
+                        if self.quirks.clip_sprites && raw_dx >= SCREEN_WIDTH as u16 {
+                            continue;
+                        }
+                        let dx = (raw_dx % SCREEN_WIDTH as u16) as u8;
+                        let index = dy as usize * SCREEN_WIDTH as usize + dx as usize;
+                        let prev_pixel = self.screen[index];
+                        let pixel = (row >> (7 - ix) & 1) * 255;
+                        if prev_pixel == 255 && pixel == 255 {
+                            self.v_registers[0xF] = 1;
+                        }
+                        self.screen[index] = prev_pixel ^ pixel;
                     }
                 }
-                0xF if instr_low == 0x07 => {
-                    // Fx07: set Vx = delay timer
-                    self.v_registers[x] = self.delay_timer;
+            }
+            Instruction::SkipKeyPressed(x) => {
+                if keyboard_state[self.v_registers[x as usize] as usize] {
+                    self.pc += 2;
                 }
-                0xF if instr_low == 0x0A => {
-                    // Fx0A: wait for key press, store pressed key in Vx
-                    self.waiting_for_key = Some(x as u8);
+            }
+            Instruction::SkipKeyNotPressed(x) => {
+                if !keyboard_state[self.v_registers[x as usize] as usize] {
+                    self.pc += 2;
                 }
-                0xF if instr_low == 0x15 => {
-                    // Fx15: set delay timer = Vx
-                    self.delay_timer = self.v_registers[x];
+            }
+            Instruction::GetDelay(x) => {
+                self.v_registers[x as usize] = self.delay_timer;
+            }
+            Instruction::WaitKey(x) => {
+                self.waiting_for_key = Some(x);
+            }
+            Instruction::SetDelay(x) => {
+                self.delay_timer = self.v_registers[x as usize];
+            }
+            Instruction::SetSound(x) => {
+                self.sound_timer = self.v_registers[x as usize];
+            }
+            Instruction::AddIndex(x) => {
+                let result = self.i_register.checked_add(self.v_registers[x as usize] as u16)
+                    .ok_or(Chip8Error::AddressOutOfBounds)?;
+                if self.quirks.overflow_flag {
+                    self.v_registers[0xF] = u8::from(result > 0x0FFF);
                 }
-                0xF if instr_low == 0x18 => {
-                    // Fx18: set sound timer = Vx
-                    self.sound_timer = self.v_registers[x];
+                self.i_register = result;
+            }
+            Instruction::SetIndexToFont(x) => {
+                self.i_register = FONT_ADDRESS + self.v_registers[x as usize] as u16 * 5;
+            }
+            Instruction::StoreBcd(x) => {
+                if self.i_register as usize + 2 >= self.memory.len() {
+                    return Err(Chip8Error::AddressOutOfBounds);
                 }
-                0xF if instr_low == 0x1E => {
-                    // Fx1E: set I = I + Vx
-                    self.i_register += self.v_registers[x] as u16;
+                self.memory[self.i_register as usize] = self.v_registers[x as usize] / 100;
+                self.memory[self.i_register as usize + 1] = (self.v_registers[x as usize] / 10) % 10;
+                self.memory[self.i_register as usize + 2] = self.v_registers[x as usize] % 10;
+            }
+            Instruction::StoreRegisters(x) => {
+                if self.i_register as usize + x as usize >= self.memory.len() {
+                    return Err(Chip8Error::AddressOutOfBounds);
                 }
-                0xF if instr_low == 0x29 => {
-                    // Fx29: set I = location of sprite for digit Vx
-                    self.i_register = FONT_ADDRESS + self.v_registers[x] as u16 * 5;
+                for i in 0..=x as usize {
+                    self.memory[self.i_register as usize + i] = self.v_registers[i];
                 }
-                0xF if instr_low == 0x33 => {
-                    // Fx33: store BCD representation of Vx in memory locations I, I+1, I+2
-                    self.memory[self.i_register as usize] = self.v_registers[x] / 100;
-                    self.memory[self.i_register as usize + 1] = (self.v_registers[x] / 10) % 10;
-                    self.memory[self.i_register as usize + 2] = self.v_registers[x] % 10;
+                if !self.quirks.load_store_no_increment {
+                    self.i_register += x as u16 + 1;
                 }
-                0xF if instr_low == 0x55 => {
-                    // Fx55: store registers V0 through Vx in memory starting at location I
-                    for i in 0..=x {
-                        self.memory[self.i_register as usize + i] = self.v_registers[i];
-                    }
+            }
+            Instruction::LoadRegisters(x) => {
+                if self.i_register as usize + x as usize >= self.memory.len() {
+                    return Err(Chip8Error::AddressOutOfBounds);
                 }
-                0xF if instr_low == 0x65 => {
-                    // Fx65: read registers V0 through Vx in memory starting at location I
-                    for i in 0..=x {
-                        self.v_registers[i] = self.memory[self.i_register as usize + i];
-                    }
+                for i in 0..=x as usize {
+                    self.v_registers[i] = self.memory[self.i_register as usize + i];
                 }
-
-                _ => {
-                    //TODO error about unknown instruction
+                if !self.quirks.load_store_no_increment {
+                    self.i_register += x as u16 + 1;
                 }
             }
+        }
+
+        self.pc += increment_pc;
+
+        Ok(())
+    }
+
+    /// Decodes and executes exactly one instruction, or handles the wait-for-key state if the
+    /// Chip-8 is currently blocked on `Fx0A`. Exposed publicly so a debugger frontend can
+    /// single-step through a program.
+    pub fn step(&mut self, keyboard_state: &KeyboardState) -> Result<(), Chip8Error> {
+        if let Some(reg) = self.waiting_for_key {
+            let key = keyboard_state.iter().position(|k| *k);
+            if let Some(k) = key {
+                self.v_registers[reg as usize] = k as u8;
+                self.waiting_for_key = None;
+            }
+            return Ok(());
+        }
 
-            self.pc += increment_pc;
+        if self.pc + 1 >= self.memory.len() {
+            return Err(Chip8Error::AddressOutOfBounds);
+        }
+
+        let opcode_bytes = [self.memory[self.pc], self.memory[self.pc + 1]];
+        match decode(opcode_bytes) {
+            Some(instruction) => self.execute(instruction, keyboard_state),
+            None => Err(Chip8Error::UnknownInstruction {
+                opcode: ((opcode_bytes[0] as u16) << 8) | opcode_bytes[1] as u16,
+                pc: self.pc,
+            }),
         }
     }
 
-    pub fn frame(&mut self, keyboard_state: &KeyboardState) {
+    /// Performs a single instruction cycle.
+    fn cycle(&mut self, keyboard_state: &KeyboardState) -> Result<(), Chip8Error> {
+        self.step(keyboard_state)
+    }
+
+    /// Decodes `count` two-byte words starting at address `start`, returning each decoded
+    /// instruction paired with its address. Words that don't decode to a known instruction are
+    /// left out, and decoding stops at the end of memory, so the result may hold fewer than
+    /// `count` entries. Intended for a debugger frontend to render a live instruction listing.
+    pub fn disassemble(&self, start: u16, count: usize) -> Vec<(u16, Instruction)> {
+        let mut result = Vec::with_capacity(count);
+        let mut address = start;
+        for _ in 0..count {
+            if address as usize + 1 >= self.memory.len() {
+                break;
+            }
+            if let Some(instruction) = decode([self.memory[address as usize], self.memory[address as usize + 1]]) {
+                result.push((address, instruction));
+            }
+            address += 2;
+        }
+        result
+    }
+
+    pub fn frame(&mut self, keyboard_state: &KeyboardState) -> Result<(), Chip8Error> {
         for _ in 0..self.cycles_per_frame {
-            self.cycle(keyboard_state);
+            self.cycle(keyboard_state)?;
         }
 
         self.delay_timer = if self.delay_timer > 0 { self.delay_timer - 1 } else { 0 };
         self.sound_timer = if self.sound_timer > 0 { self.sound_timer - 1 } else { 0 };
+
+        Ok(())
     }
     
-    pub fn new(rom_data: &[u8]) -> Chip8 {
+    pub fn new(rom_data: &[u8], quirks: Quirks, rand_source: Box<dyn RandSource>) -> Chip8 {
         let mut new = Chip8 {
             memory: [0; 4096],
             v_registers: [0; 16],
@@ -296,8 +414,10 @@ impl Chip8 {
             sound_timer: 0,
             delay_timer: 0,
             waiting_for_key: None,
-            alternative_shift_mode: false,
-            cycles_per_frame: 8
+            quirks,
+            rand_source,
+            cycles_per_frame: 8,
+            draw_flag: false,
         };
 
         for (i, b) in FONT_DATA.iter().enumerate() {
@@ -310,4 +430,110 @@ impl Chip8 {
 
         new
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NullRand;
+
+    impl RandSource for NullRand {
+        fn next_u8(&mut self) -> u8 {
+            0
+        }
+    }
+
+    fn new_chip8(rom_data: &[u8]) -> Chip8 {
+        Chip8::new(rom_data, Quirks::default(), Box::new(NullRand))
+    }
+
+    #[test]
+    fn decode_rejects_unknown_opcode() {
+        assert_eq!(decode([0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn call_past_stack_depth_returns_stack_overflow() {
+        let mut chip8 = new_chip8(&[]);
+        chip8.stack_pointer = chip8.stack.len();
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.execute(Instruction::Call(0x200), &keyboard_state),
+            Err(Chip8Error::StackOverflow)
+        );
+    }
+
+    #[test]
+    fn return_with_empty_stack_returns_stack_underflow() {
+        let mut chip8 = new_chip8(&[]);
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.execute(Instruction::Return, &keyboard_state),
+            Err(Chip8Error::StackUnderflow)
+        );
+    }
+
+    #[test]
+    fn add_index_overflow_returns_address_out_of_bounds() {
+        let mut chip8 = new_chip8(&[]);
+        chip8.i_register = 0xFFFF;
+        chip8.v_registers[0] = 1;
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.execute(Instruction::AddIndex(0), &keyboard_state),
+            Err(Chip8Error::AddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn store_bcd_past_memory_end_returns_address_out_of_bounds() {
+        let mut chip8 = new_chip8(&[]);
+        chip8.i_register = 0x0FFF;
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.execute(Instruction::StoreBcd(0), &keyboard_state),
+            Err(Chip8Error::AddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn store_registers_past_memory_end_returns_address_out_of_bounds() {
+        let mut chip8 = new_chip8(&[]);
+        chip8.i_register = 0x0FFF;
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.execute(Instruction::StoreRegisters(1), &keyboard_state),
+            Err(Chip8Error::AddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn load_registers_past_memory_end_returns_address_out_of_bounds() {
+        let mut chip8 = new_chip8(&[]);
+        chip8.i_register = 0x0FFF;
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.execute(Instruction::LoadRegisters(1), &keyboard_state),
+            Err(Chip8Error::AddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn step_on_unknown_opcode_returns_unknown_instruction() {
+        // 0x0000 doesn't decode to any known instruction.
+        let mut chip8 = new_chip8(&[0x00, 0x00]);
+
+        let keyboard_state: KeyboardState = [false; 16];
+        assert_eq!(
+            chip8.step(&keyboard_state),
+            Err(Chip8Error::UnknownInstruction { opcode: 0, pc: ROM_START_ADDRESS })
+        );
+    }
 }
\ No newline at end of file
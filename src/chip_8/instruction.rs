@@ -0,0 +1,166 @@
+use std::fmt;
+
+/// A single decoded Chip-8 instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// 00E0: clear the screen.
+    ClearScreen,
+    /// 00EE: return from a subroutine.
+    Return,
+    /// 1nnn: jump to address nnn.
+    Jump(u16),
+    /// 2nnn: call subroutine at nnn.
+    Call(u16),
+    /// 3xkk: skip next instruction if Vx == kk.
+    SkipEqualImm { x: u8, kk: u8 },
+    /// 4xkk: skip next instruction if Vx != kk.
+    SkipNotEqualImm { x: u8, kk: u8 },
+    /// 5xy0: skip next instruction if Vx == Vy.
+    SkipEqual { x: u8, y: u8 },
+    /// 6xkk: set Vx = kk.
+    SetReg { x: u8, kk: u8 },
+    /// 7xkk: set Vx = Vx + kk.
+    AddReg { x: u8, kk: u8 },
+    /// 8xy0: set Vx = Vy.
+    Copy { x: u8, y: u8 },
+    /// 8xy1: set Vx = Vx | Vy.
+    Or { x: u8, y: u8 },
+    /// 8xy2: set Vx = Vx & Vy.
+    And { x: u8, y: u8 },
+    /// 8xy3: set Vx = Vx ^ Vy.
+    Xor { x: u8, y: u8 },
+    /// 8xy4: set Vx = Vx + Vy, VF = carry.
+    Add { x: u8, y: u8 },
+    /// 8xy5: set Vx = Vx - Vy, VF = not borrow.
+    Sub { x: u8, y: u8 },
+    /// 8xy6: set Vx = Vy >> 1, VF = LSB(Vy) (or Vx >> 1, VF = LSB(Vx) under the shift quirk).
+    ShiftRight { x: u8, y: u8 },
+    /// 8xy7: set Vx = Vy - Vx, VF = not borrow.
+    SubReverse { x: u8, y: u8 },
+    /// 8xyE: set Vx = Vy << 1, VF = MSB(Vy) (or Vx << 1, VF = MSB(Vx) under the shift quirk).
+    ShiftLeft { x: u8, y: u8 },
+    /// 9xy0: skip next instruction if Vx != Vy.
+    SkipNotEqual { x: u8, y: u8 },
+    /// Annn: set I = nnn.
+    SetIndex(u16),
+    /// Bnnn: jump to V0 + nnn (or Vx + nnn under the jump quirk).
+    JumpWithOffset(u16),
+    /// Cxkk: set Vx = random & kk.
+    Random { x: u8, kk: u8 },
+    /// Dxyn: draw an n-byte sprite at (Vx, Vy), set VF = collision.
+    Draw { x: u8, y: u8, n: u8 },
+    /// Ex9E: skip next instruction if key Vx is pressed.
+    SkipKeyPressed(u8),
+    /// ExA1: skip next instruction if key Vx is not pressed.
+    SkipKeyNotPressed(u8),
+    /// Fx07: set Vx = delay timer.
+    GetDelay(u8),
+    /// Fx0A: wait for a key press, store the pressed key in Vx.
+    WaitKey(u8),
+    /// Fx15: set delay timer = Vx.
+    SetDelay(u8),
+    /// Fx18: set sound timer = Vx.
+    SetSound(u8),
+    /// Fx1E: set I = I + Vx.
+    AddIndex(u8),
+    /// Fx29: set I = location of the sprite for digit Vx.
+    SetIndexToFont(u8),
+    /// Fx33: store the BCD representation of Vx in memory at I, I+1, I+2.
+    StoreBcd(u8),
+    /// Fx55: store registers V0 through Vx in memory starting at I.
+    StoreRegisters(u8),
+    /// Fx65: read registers V0 through Vx from memory starting at I.
+    LoadRegisters(u8),
+}
+
+/// Decodes the two bytes of a Chip-8 instruction (big-endian, as stored in memory), or `None` if
+/// the bit pattern doesn't correspond to any known instruction.
+pub fn decode(bytes: [u8; 2]) -> Option<Instruction> {
+    let instr_high = bytes[0];
+    let instr_low = bytes[1];
+    let instruction = ((instr_high as u16) << 8) | instr_low as u16;
+    let high_nibble = instr_high >> 4;
+    let low_nibble = instr_low & 0xf;
+    let x = (instruction >> 8 & 0xf) as u8;
+    let y = (instruction >> 4 & 0xf) as u8;
+    let nnn = instruction & 0xfff;
+
+    Some(match high_nibble {
+        0 if instruction == 0x00E0 => Instruction::ClearScreen,
+        0 if instruction == 0x00EE => Instruction::Return,
+        1 => Instruction::Jump(nnn),
+        2 => Instruction::Call(nnn),
+        3 => Instruction::SkipEqualImm { x, kk: instr_low },
+        4 => Instruction::SkipNotEqualImm { x, kk: instr_low },
+        5 if low_nibble == 0 => Instruction::SkipEqual { x, y },
+        6 => Instruction::SetReg { x, kk: instr_low },
+        7 => Instruction::AddReg { x, kk: instr_low },
+        8 if low_nibble == 0 => Instruction::Copy { x, y },
+        8 if low_nibble == 1 => Instruction::Or { x, y },
+        8 if low_nibble == 2 => Instruction::And { x, y },
+        8 if low_nibble == 3 => Instruction::Xor { x, y },
+        8 if low_nibble == 4 => Instruction::Add { x, y },
+        8 if low_nibble == 5 => Instruction::Sub { x, y },
+        8 if low_nibble == 6 => Instruction::ShiftRight { x, y },
+        8 if low_nibble == 7 => Instruction::SubReverse { x, y },
+        8 if low_nibble == 0xE => Instruction::ShiftLeft { x, y },
+        9 if low_nibble == 0 => Instruction::SkipNotEqual { x, y },
+        0xA => Instruction::SetIndex(nnn),
+        0xB => Instruction::JumpWithOffset(nnn),
+        0xC => Instruction::Random { x, kk: instr_low },
+        0xD => Instruction::Draw { x, y, n: low_nibble },
+        0xE if instr_low == 0x9E => Instruction::SkipKeyPressed(x),
+        0xE if instr_low == 0xA1 => Instruction::SkipKeyNotPressed(x),
+        0xF if instr_low == 0x07 => Instruction::GetDelay(x),
+        0xF if instr_low == 0x0A => Instruction::WaitKey(x),
+        0xF if instr_low == 0x15 => Instruction::SetDelay(x),
+        0xF if instr_low == 0x18 => Instruction::SetSound(x),
+        0xF if instr_low == 0x1E => Instruction::AddIndex(x),
+        0xF if instr_low == 0x29 => Instruction::SetIndexToFont(x),
+        0xF if instr_low == 0x33 => Instruction::StoreBcd(x),
+        0xF if instr_low == 0x55 => Instruction::StoreRegisters(x),
+        0xF if instr_low == 0x65 => Instruction::LoadRegisters(x),
+        _ => return None,
+    })
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::ClearScreen => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::Jump(nnn) => write!(f, "JP 0x{:03X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL 0x{:03X}", nnn),
+            Instruction::SkipEqualImm { x, kk } => write!(f, "SE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SkipNotEqualImm { x, kk } => write!(f, "SNE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SkipEqual { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::SetReg { x, kk } => write!(f, "LD V{:X}, 0x{:02X}", x, kk),
+            Instruction::AddReg { x, kk } => write!(f, "ADD V{:X}, 0x{:02X}", x, kk),
+            Instruction::Copy { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::Add { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubReverse { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipNotEqual { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::SetIndex(nnn) => write!(f, "LD I, 0x{:03X}", nnn),
+            Instruction::JumpWithOffset(nnn) => write!(f, "JP V0, 0x{:03X}", nnn),
+            Instruction::Random { x, kk } => write!(f, "RND V{:X}, 0x{:02X}", x, kk),
+            Instruction::Draw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipKeyPressed(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipKeyNotPressed(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::GetDelay(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitKey(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddIndex(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::SetIndexToFont(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::StoreBcd(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::StoreRegisters(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadRegisters(x) => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
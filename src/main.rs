@@ -1,5 +1,3 @@
-mod chip_8;
-
 use std::collections::HashMap;
 use std::env;
 use sdl2::pixels::{Color, PixelFormatEnum};
@@ -7,7 +5,36 @@ use sdl2::event::Event;
 use sdl2::keyboard::{Keycode, Scancode};
 use std::time::Duration;
 use sdl2::render::TextureAccess;
-use crate::chip_8::KeyboardState;
+use sdl2::audio::{AudioCallback, AudioSpecDesired};
+use rust_chip8::chip_8;
+use rust_chip8::chip_8::{KeyboardState, RandSource};
+
+/// A simple square-wave tone generator, used to produce the Chip-8's beep.
+struct SquareWave {
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+}
+
+impl AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = if self.phase <= 0.5 { self.volume } else { -self.volume };
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+    }
+}
+
+/// Randomness for `Cxkk`, backed by the `rand` crate.
+struct OsRandSource;
+
+impl RandSource for OsRandSource {
+    fn next_u8(&mut self) -> u8 {
+        rand::random()
+    }
+}
 
 pub fn main() {
     const SCALE: u32 = 6;
@@ -21,7 +48,11 @@ pub fn main() {
         (Scancode::Z, 0xA), (Scancode::X, 0x0), (Scancode::C, 0xB), (Scancode::V, 0xF),
     ]);
 
-    let mut chip8 = chip_8::Chip8::new(std::fs::read(args.get(1).expect("path to ROM required")).unwrap().as_slice());
+    let mut chip8 = chip_8::Chip8::new(
+        std::fs::read(args.get(1).expect("path to ROM required")).unwrap().as_slice(),
+        chip_8::Quirks::default(),
+        Box::new(OsRandSource),
+    );
 
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
@@ -33,6 +64,20 @@ pub fn main() {
 
     let mut canvas = window.into_canvas().build().unwrap();
 
+    let audio_subsystem = sdl_context.audio().unwrap();
+    let audio_spec = AudioSpecDesired {
+        freq: Some(44100),
+        channels: Some(1),
+        samples: None,
+    };
+    let audio_device = audio_subsystem.open_playback(None, &audio_spec, |spec| {
+        SquareWave {
+            phase_inc: 440.0 / spec.freq as f32,
+            phase: 0.0,
+            volume: 0.25,
+        }
+    }).unwrap();
+
     let texture_creator = canvas.texture_creator();
     let mut texture = texture_creator.create_texture(PixelFormatEnum::RGB332, TextureAccess::Streaming, chip_8::SCREEN_WIDTH as u32, chip_8::SCREEN_HEIGHT as u32).unwrap();
 
@@ -43,7 +88,6 @@ pub fn main() {
     canvas.present();
     let mut event_pump = sdl_context.event_pump().unwrap();
     'running: loop {
-        canvas.clear();
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } |
@@ -64,12 +108,25 @@ pub fn main() {
             }
         }
 
-        chip8.frame(&keyboard_state);
+        if let Err(e) = chip8.frame(&keyboard_state) {
+            eprintln!("chip8 execution error: {:?}", e);
+            break 'running;
+        }
 
-        texture.update(None, &chip8.screen, chip_8::SCREEN_WIDTH as usize);
-        canvas.copy(&texture, None, None);
+        if chip8.is_beeping() {
+            audio_device.resume();
+        } else {
+            audio_device.pause();
+        }
+
+        if chip8.draw_flag {
+            canvas.clear();
+            texture.update(None, &chip8.screen, chip_8::SCREEN_WIDTH as usize);
+            canvas.copy(&texture, None, None);
+            canvas.present();
+            chip8.draw_flag = false;
+        }
 
-        canvas.present();
         ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
     }
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use wasm_bindgen::prelude::*;
+
+use crate::chip_8::{Chip8, KeyboardState, Quirks, RandSource, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Randomness for `Cxkk`, sourced from `Math.random` since wasm has no `rand` backend of its own.
+struct JsRandSource;
+
+impl RandSource for JsRandSource {
+    fn next_u8(&mut self) -> u8 {
+        (js_sys::Math::random() * 256.0) as u8
+    }
+}
+
+/// A thin wasm-bindgen frontend around [`Chip8`]. A JS host owns the render/input loop: it
+/// constructs one of these from ROM bytes, calls `frame` once per animation frame, and reads
+/// `screen_ptr`/`screen_len` to draw the framebuffer onto a canvas.
+#[wasm_bindgen]
+pub struct WasmChip8 {
+    core: Chip8,
+}
+
+#[wasm_bindgen]
+impl WasmChip8 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8]) -> WasmChip8 {
+        WasmChip8 {
+            core: Chip8::new(rom, Quirks::default(), Box::new(JsRandSource)),
+        }
+    }
+
+    /// Advances the emulator by one frame. `keys` must hold exactly 16 bytes, one per Chip-8 key,
+    /// non-zero meaning "currently pressed".
+    pub fn frame(&mut self, keys: &[u8]) -> Result<(), JsValue> {
+        let mut keyboard_state: KeyboardState = [false; 16];
+        for (state, key) in keyboard_state.iter_mut().zip(keys) {
+            *state = *key != 0;
+        }
+
+        self.core
+            .frame(&keyboard_state)
+            .map_err(|e| JsValue::from_str(&format!("chip8 execution error: {:?}", e)))
+    }
+
+    /// A pointer to the start of the framebuffer, one byte per pixel (0 or 255).
+    pub fn screen_ptr(&self) -> *const u8 {
+        self.core.screen.as_ptr()
+    }
+
+    /// The length of the framebuffer, `SCREEN_WIDTH * SCREEN_HEIGHT`.
+    pub fn screen_len(&self) -> usize {
+        self.core.screen.len()
+    }
+
+    pub fn screen_width(&self) -> u32 {
+        SCREEN_WIDTH as u32
+    }
+
+    pub fn screen_height(&self) -> u32 {
+        SCREEN_HEIGHT as u32
+    }
+
+    pub fn is_beeping(&self) -> bool {
+        self.core.is_beeping()
+    }
+}